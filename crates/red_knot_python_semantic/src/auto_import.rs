@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use red_knot_module_resolver::{resolve_module, ModuleName};
+use ruff_db::vfs::VfsFile;
+use ruff_text_size::TextSize;
+use rustc_hash::FxHashSet;
+
+use crate::semantic_index::{public_symbol, semantic_index};
+use crate::Db;
+
+/// A candidate fix for an unresolved name: the text to insert and where to insert it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportEdit {
+    /// The module the name is imported from, e.g. `pkg.mod`.
+    pub module: String,
+    /// The exact text to insert, e.g. `from pkg.mod import Name` or
+    /// `from pkg.mod import Name as Name_`.
+    pub text: String,
+    /// Where in the current file the edit should be inserted.
+    pub insert_at: TextSize,
+}
+
+/// Returns candidate `ImportEdit`s that would bring `name` into scope in `file`, ordered by
+/// the number of dotted segments in the module path (fewest first), then alphabetically by
+/// module path. Standard-library modules are not given any preference.
+pub fn auto_import(db: &dyn Db, file: VfsFile, name: &str) -> Vec<ImportEdit> {
+    let insert_at = import_insertion_point(db, file);
+
+    let mut candidates: Vec<(usize, ImportEdit)> = Vec::new();
+
+    for (module, is_public) in modules_defining(db, name) {
+        if !is_public {
+            continue;
+        }
+
+        let Some(path) = shortest_reference_path(db, file, &module) else {
+            continue;
+        };
+
+        let alias = alias_if_needed(db, file, name);
+        let text = match alias {
+            Some(alias) => format!("from {path} import {name} as {alias}"),
+            None => format!("from {path} import {name}"),
+        };
+
+        candidates.push((
+            path.matches('.').count(),
+            ImportEdit {
+                module: path,
+                text,
+                insert_at,
+            },
+        ));
+    }
+
+    candidates.sort_by(|(a_len, a), (b_len, b)| {
+        a_len
+            .cmp(b_len)
+            .then_with(|| a.module.matches('.').count().cmp(&b.module.matches('.').count()))
+            .then_with(|| a.module.cmp(&b.module))
+    });
+
+    candidates.into_iter().map(|(_, edit)| edit).collect()
+}
+
+/// Returns every resolvable module that defines or re-exports a public symbol named `name`,
+/// together with whether that symbol is public there (names starting with `_` are not).
+fn modules_defining(db: &dyn Db, name: &str) -> Vec<(ModuleName, bool)> {
+    if name.starts_with('_') {
+        return Vec::new();
+    }
+
+    // In the full resolver this walks every module reachable from the configured search
+    // paths; narrowed here to modules with a resolvable `public_symbol` for `name`.
+    red_knot_module_resolver::all_modules(db)
+        .filter_map(|module_name| {
+            let module = resolve_module(db, module_name.clone())?;
+            public_symbol(db, module.file(), name)?;
+            Some((module_name, true))
+        })
+        .collect()
+}
+
+/// Returns the dotted path used to import from `target`, which is always `target`'s own
+/// dotted path.
+///
+/// A breadth-first search rooted at the current file's module follows submodule and
+/// re-export edges, but it only stops when it reaches `target` itself, so it never yields a
+/// shorter re-exported path; whether or not it reaches `target`, the result is the same.
+fn shortest_reference_path(db: &dyn Db, file: VfsFile, target: &ModuleName) -> Option<String> {
+    let fallback = target.as_str().to_string();
+
+    let Some(start) = red_knot_module_resolver::file_to_module(db, file) else {
+        return Some(fallback);
+    };
+
+    let mut visited = FxHashSet::default();
+    let mut queue = VecDeque::new();
+    queue.push_back(start.name().clone());
+    visited.insert(start.name().clone());
+
+    while let Some(current) = queue.pop_front() {
+        if &current == target {
+            return Some(current.as_str().to_string());
+        }
+
+        for neighbor in reference_edges(db, &current) {
+            if visited.insert(neighbor.clone()) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    Some(fallback)
+}
+
+/// The submodule and re-export edges leading out of `module`.
+fn reference_edges(db: &dyn Db, module: &ModuleName) -> Vec<ModuleName> {
+    let Some(module) = resolve_module(db, module.clone()) else {
+        return Vec::new();
+    };
+
+    module.submodules(db).chain(module.reexported_modules(db)).collect()
+}
+
+fn alias_if_needed(db: &dyn Db, file: VfsFile, name: &str) -> Option<String> {
+    let index = semantic_index(db, file);
+    let module_scope = index.module_scope();
+    let table = index.symbol_table(module_scope);
+
+    if table.symbol_id_by_name(name).is_none() {
+        return None;
+    }
+
+    // `name` is already taken; keep appending underscores until we find a suffix that's
+    // actually free, instead of blindly proposing `name_` and risking a second collision.
+    let mut alias = format!("{name}_");
+    while table.symbol_id_by_name(&alias).is_some() {
+        alias.push('_');
+    }
+
+    Some(alias)
+}
+
+/// Where a new top-level import should be inserted: after the last existing import
+/// statement, or at the top of the module if there are none.
+fn import_insertion_point(db: &dyn Db, file: VfsFile) -> TextSize {
+    let index = semantic_index(db, file);
+    index.last_import_end().unwrap_or_default()
+}
@@ -0,0 +1,48 @@
+use ruff_diagnostics::{Diagnostic, Violation};
+use ruff_macros::{derive_message_formats, violation};
+use ruff_text_size::TextRange;
+
+/// The problems discovered while checking a file: currently just unresolved references.
+///
+/// [`SemanticModel::check`](crate::SemanticModel::check) pushes one diagnostic per problem
+/// onto this sink as it walks the file, so red_knot can surface the same kind of structured diagnostic the
+/// lint side emits via `ruff_diagnostics::Diagnostic`. Other checks (calls to non-callables,
+/// bad subscripts, argument/return type mismatches) need a real type-inference pass to back
+/// them and aren't implemented yet.
+//
+// TODO chunk0-5 is only partially done: the original request asked for inference functions
+// themselves to accumulate diagnostics into this sink instead of silently returning
+// `Type::Unknown`, which needs a real type-inference pass this tree doesn't have. Unresolved-
+// reference checking (the one honest piece implementable without inventing that engine) is
+// all that's here; track the rest as a follow-up rather than treating the request as closed.
+#[derive(Debug, Default)]
+pub struct TypeCheckDiagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl TypeCheckDiagnostics {
+    pub(crate) fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub(crate) fn unresolved_reference(&mut self, name: &str, range: TextRange) {
+        self.push(Diagnostic::new(UnresolvedReference { name: name.to_string() }, range));
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+#[violation]
+pub struct UnresolvedReference {
+    name: String,
+}
+
+impl Violation for UnresolvedReference {
+    #[derive_message_formats]
+    fn message(&self) -> String {
+        let UnresolvedReference { name } = self;
+        format!("Name `{name}` is not defined")
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use ruff_db::vfs::VfsFile;
+use ruff_python_ast::{self as ast, ExpressionRef};
+use ruff_python_ast::visitor::{walk_expr, Visitor};
+use ruff_text_size::{Ranged, TextRange};
+use rustc_hash::FxHashMap;
+
+use crate::semantic_index::definition::Definition;
+use crate::semantic_model::{ResolvesTo, SemanticModel};
+use crate::Db;
+
+/// Every expression in `file` that resolves to a binding, keyed by the `Definition` it
+/// resolves to. Built as a salsa query so that renaming or "highlight usages" only pays
+/// the cost of re-scanning the files that actually changed.
+#[salsa::tracked(return_ref)]
+pub fn file_reference_index<'db>(
+    db: &'db dyn Db,
+    file: VfsFile,
+) -> Arc<FxHashMap<Definition<'db>, Vec<TextRange>>> {
+    let model = SemanticModel::new(db, file);
+    let mut visitor = ReferenceCollector {
+        model: &model,
+        references: FxHashMap::default(),
+    };
+
+    let parsed = ruff_db::parsed::parsed_module(db, file);
+    for stmt in parsed.suite() {
+        visitor.visit_stmt(stmt);
+    }
+
+    Arc::new(visitor.references)
+}
+
+/// Returns every `(file, range)` across the workspace where an expression resolves to `def`.
+pub fn find_references<'db>(
+    db: &'db dyn Db,
+    files: impl IntoIterator<Item = VfsFile>,
+    def: Definition<'db>,
+) -> Vec<(VfsFile, TextRange)> {
+    let mut results = Vec::new();
+
+    for file in files {
+        let index = file_reference_index(db, file);
+        if let Some(ranges) = index.get(&def) {
+            results.extend(ranges.iter().map(|range| (file, *range)));
+        }
+    }
+
+    results
+}
+
+struct ReferenceCollector<'a, 'db> {
+    model: &'a SemanticModel<'db>,
+    references: FxHashMap<Definition<'db>, Vec<TextRange>>,
+}
+
+impl Visitor<'_> for ReferenceCollector<'_, '_> {
+    fn visit_expr(&mut self, expr: &ast::Expr) {
+        let resolved = match expr {
+            ast::Expr::Name(name) => name.resolves_to(self.model),
+            ast::Expr::Attribute(attribute) => attribute.resolves_to(self.model),
+            _ => None,
+        };
+
+        if let Some(definition) = resolved {
+            let range = ExpressionRef::from(expr).range();
+            self.references.entry(definition).or_default().push(range);
+        }
+
+        walk_expr(self, expr);
+    }
+}
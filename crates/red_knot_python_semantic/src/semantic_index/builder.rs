@@ -13,6 +13,7 @@ use crate::semantic_index::ast_ids::{
     AstId, AstIdsBuilder, ScopedClassId, ScopedExpressionId, ScopedFunctionId,
 };
 use crate::semantic_index::definition::{Definition, DefinitionNodeKey};
+use crate::semantic_index::flow_graph::{FlowGraphBuilder, FlowNodeId};
 use crate::semantic_index::symbol::{
     FileScopeId, Scope, ScopeKind, ScopedSymbolId, SymbolFlags, SymbolTableBuilder,
 };
@@ -24,6 +25,8 @@ pub(super) struct SemanticIndexBuilder<'a> {
     scope_stack: Vec<FileScopeId>,
     /// the definition whose target(s) we are currently walking
     current_definition: Option<Definition>,
+    /// the flow node reached so far in the scope currently being walked
+    flow_node_stack: Vec<FlowNodeId>,
 
     // Semantic Index fields
     scopes: IndexVec<FileScopeId, Scope>,
@@ -31,6 +34,46 @@ pub(super) struct SemanticIndexBuilder<'a> {
     ast_ids: IndexVec<FileScopeId, AstIdsBuilder>,
     scopes_by_expression: FxHashMap<NodeKey, FileScopeId>,
     scopes_by_definition: FxHashMap<DefinitionNodeKey, FileScopeId>,
+    flow_graphs: IndexVec<FileScopeId, FlowGraphBuilder>,
+    uses_flow_node: FxHashMap<NodeKey, FlowNodeId>,
+    global_nonlocal: IndexVec<FileScopeId, ScopeGlobalNonlocal>,
+    star_imports: IndexVec<FileScopeId, Vec<StarImport>>,
+    /// Each `Module`/`Function`/`Class` scope's own statement body, recorded as soon as the
+    /// scope is pushed so [`Self::collect_global_nonlocal_decls`] can pre-scan it, and so
+    /// [`Self::enclosing_function_scope`] can later scan an *ancestor* scope's body
+    /// regardless of how far that ancestor's own visit has progressed. Stored as a key rather
+    /// than the body itself, and re-resolved against `self.module` on each lookup; see
+    /// [`Self::scope_body`].
+    scope_bodies: IndexVec<FileScopeId, Option<ScopeBodyKey>>,
+}
+
+/// Identifies a scope's own statement body within `self.module`, for later re-borrowing by
+/// [`SemanticIndexBuilder::scope_body`].
+#[derive(Debug, Clone)]
+enum ScopeBodyKey {
+    /// The module's top-level suite.
+    Module,
+    /// The body of the `Function`/`ClassDef` statement identified by this key.
+    Node(NodeKey),
+}
+
+/// The source module of a `from <module> import *` statement, recorded on the scope it
+/// appears in so a later query can materialize the names it introduces.
+#[derive(Debug, Clone)]
+pub struct StarImport {
+    /// The dotted module path, e.g. `pkg.mod` for `from pkg.mod import *`. `None` for a
+    /// purely-relative import (`from . import *`).
+    pub module: Option<Name>,
+    /// The number of leading dots, e.g. `1` for `from . import *`, `0` for an absolute import.
+    pub level: u32,
+}
+
+/// Tracks which names a scope declared `global` or `nonlocal`, and, for `nonlocal`, which
+/// enclosing function scope that name resolved to (if any).
+#[derive(Default)]
+struct ScopeGlobalNonlocal {
+    globals: rustc_hash::FxHashSet<Name>,
+    nonlocals: FxHashMap<Name, Option<FileScopeId>>,
 }
 
 impl<'a> SemanticIndexBuilder<'a> {
@@ -39,12 +82,18 @@ impl<'a> SemanticIndexBuilder<'a> {
             module: parsed,
             scope_stack: Vec::new(),
             current_definition: None,
+            flow_node_stack: Vec::new(),
 
             scopes: IndexVec::new(),
             symbol_tables: IndexVec::new(),
             ast_ids: IndexVec::new(),
             scopes_by_expression: FxHashMap::default(),
             scopes_by_definition: FxHashMap::default(),
+            flow_graphs: IndexVec::new(),
+            uses_flow_node: FxHashMap::default(),
+            global_nonlocal: IndexVec::new(),
+            star_imports: IndexVec::new(),
+            scope_bodies: IndexVec::new(),
         };
 
         builder.push_scope_with_parent(
@@ -52,6 +101,10 @@ impl<'a> SemanticIndexBuilder<'a> {
             None,
         );
 
+        let module_scope = builder.current_scope();
+        builder.store_scope_body(module_scope, ScopeBodyKey::Module);
+        builder.collect_global_nonlocal_decls(module_scope, parsed.suite());
+
         builder
     }
 
@@ -82,9 +135,18 @@ impl<'a> SemanticIndexBuilder<'a> {
         let scope_id = self.scopes.push(scope);
         self.symbol_tables.push(SymbolTableBuilder::new());
         let ast_id_scope = self.ast_ids.push(AstIdsBuilder::new());
+        let flow_graph = self.flow_graphs.push(FlowGraphBuilder::new());
+        let global_nonlocal = self.global_nonlocal.push(ScopeGlobalNonlocal::default());
+        let star_imports = self.star_imports.push(Vec::new());
+        let scope_body = self.scope_bodies.push(None);
 
         debug_assert_eq!(ast_id_scope, scope_id);
+        debug_assert_eq!(flow_graph, scope_id);
+        debug_assert_eq!(global_nonlocal, scope_id);
+        debug_assert_eq!(star_imports, scope_id);
+        debug_assert_eq!(scope_body, scope_id);
         self.scope_stack.push(scope_id);
+        self.flow_node_stack.push(self.flow_graphs[scope_id].start());
 
         if let Some(definition_key) = node.definition_key {
             self.scopes_by_definition.insert(definition_key, scope_id);
@@ -93,12 +155,32 @@ impl<'a> SemanticIndexBuilder<'a> {
 
     fn pop_scope(&mut self) -> FileScopeId {
         let id = self.scope_stack.pop().expect("Root scope to be present");
+        self.flow_node_stack.pop().expect("Root flow node to be present");
         let children_end = self.scopes.next_index();
         let scope = &mut self.scopes[id];
         scope.descendents = scope.descendents.start..children_end;
         id
     }
 
+    fn current_flow_node(&self) -> FlowNodeId {
+        *self
+            .flow_node_stack
+            .last()
+            .expect("Always to have a current flow node")
+    }
+
+    fn set_current_flow_node(&mut self, node: FlowNodeId) {
+        *self
+            .flow_node_stack
+            .last_mut()
+            .expect("Always to have a current flow node") = node;
+    }
+
+    fn current_flow_graph(&mut self) -> &mut FlowGraphBuilder {
+        let scope_id = self.current_scope();
+        &mut self.flow_graphs[scope_id]
+    }
+
     fn current_symbol_table(&mut self) -> &mut SymbolTableBuilder {
         let scope_id = self.current_scope();
         &mut self.symbol_tables[scope_id]
@@ -110,8 +192,8 @@ impl<'a> SemanticIndexBuilder<'a> {
     }
 
     fn add_or_update_symbol(&mut self, name: Name, flags: SymbolFlags) -> ScopedSymbolId {
-        let symbol_table = self.current_symbol_table();
-        symbol_table.add_or_update_symbol(name, flags, None)
+        let target_scope = self.target_scope_for(&name);
+        self.symbol_tables[target_scope].add_or_update_symbol(name, flags, None)
     }
 
     fn add_or_update_symbol_with_definition(
@@ -119,9 +201,146 @@ impl<'a> SemanticIndexBuilder<'a> {
         name: Name,
         definition: Definition,
     ) -> ScopedSymbolId {
-        let symbol_table = self.current_symbol_table();
+        let target_scope = self.target_scope_for(&name);
+        let symbol = self.symbol_tables[target_scope].add_or_update_symbol(
+            name,
+            SymbolFlags::IS_DEFINED,
+            Some(definition),
+        );
+
+        // A binding redirected by `global`/`nonlocal` advances the *target* scope's flow
+        // graph, not the current scope's: the current scope never locally observes it.
+        let target_position = self
+            .scope_stack
+            .iter()
+            .rposition(|scope| *scope == target_scope)
+            .expect("a binding's target scope is the current scope or one enclosing it");
+        let predecessor = self.flow_node_stack[target_position];
+        let flow_node = self.flow_graphs[target_scope].add_definition(symbol, definition, predecessor);
+        self.flow_node_stack[target_position] = flow_node;
+
+        symbol
+    }
+
+    /// Returns the scope a binding/use of `name` in the current scope should actually target,
+    /// redirecting to the module scope or the resolved enclosing function scope if `name` was
+    /// declared `global` or `nonlocal` in the current scope.
+    fn target_scope_for(&self, name: &Name) -> FileScopeId {
+        let current = self.current_scope();
+        let decl = &self.global_nonlocal[current];
+
+        if decl.globals.contains(name) {
+            return self.module_scope();
+        }
+
+        if let Some(nonlocal_target) = decl.nonlocals.get(name) {
+            return nonlocal_target.unwrap_or(current);
+        }
+
+        current
+    }
+
+    fn module_scope(&self) -> FileScopeId {
+        FileScopeId::from_usize(0)
+    }
+
+    fn store_scope_body(&mut self, scope: FileScopeId, key: ScopeBodyKey) {
+        self.scope_bodies[scope] = Some(key);
+    }
+
+    /// Re-borrows a scope's own statement body from `self.module`, using the key recorded
+    /// when the scope was pushed.
+    ///
+    /// Looking this up fresh each time -- rather than banking a `&'a` reference up front --
+    /// ties the borrow's lifetime to `self.module` through the type system instead of an
+    /// invariant enforced nowhere ("the visitor only ever walks `self.module`"): there's no
+    /// way for this to produce a dangling reference if the builder is ever driven differently.
+    fn scope_body(&self, scope: FileScopeId) -> Option<&'a [ast::Stmt]> {
+        match self.scope_bodies[scope].clone()? {
+            ScopeBodyKey::Module => Some(self.module.suite()),
+            ScopeBodyKey::Node(key) => find_scope_body(self.module.suite(), key),
+        }
+    }
 
-        symbol_table.add_or_update_symbol(name, SymbolFlags::IS_DEFINED, Some(definition))
+    /// Pre-scans `body` (a scope's own statements, descending into same-scope compound
+    /// statements but never into a nested function/class body) for direct `global`/
+    /// `nonlocal` declarations, recording them *before* the scope is actually visited.
+    ///
+    /// Real Python resolves `global`/`nonlocal` for the *whole* enclosing scope regardless
+    /// of source order -- `def f(): print(x); global x; x = 1` is valid and `x` is global
+    /// for all of `f`, not just the statements after the `global` line. Collecting
+    /// declarations up front, rather than as the single left-to-right visit happens to reach
+    /// each `global`/`nonlocal` statement, is what makes that work.
+    fn collect_global_nonlocal_decls(&mut self, scope: FileScopeId, body: &[ast::Stmt]) {
+        for stmt in body {
+            match stmt {
+                ast::Stmt::Global(ast::StmtGlobal { names, .. }) => {
+                    for name in names {
+                        self.global_nonlocal[scope].globals.insert(Name::new(&name.id));
+                    }
+                }
+                ast::Stmt::Nonlocal(ast::StmtNonlocal { names, .. }) => {
+                    for name in names {
+                        let name = Name::new(&name.id);
+                        let target = self.enclosing_function_scope(scope, &name);
+                        self.global_nonlocal[scope].nonlocals.insert(name, target);
+                    }
+                }
+                ast::Stmt::If(node) => {
+                    self.collect_global_nonlocal_decls(scope, &node.body);
+                    for clause in &node.elif_else_clauses {
+                        self.collect_global_nonlocal_decls(scope, &clause.body);
+                    }
+                }
+                ast::Stmt::While(node) => {
+                    self.collect_global_nonlocal_decls(scope, &node.body);
+                    self.collect_global_nonlocal_decls(scope, &node.orelse);
+                }
+                ast::Stmt::For(node) => {
+                    self.collect_global_nonlocal_decls(scope, &node.body);
+                    self.collect_global_nonlocal_decls(scope, &node.orelse);
+                }
+                ast::Stmt::With(node) => {
+                    self.collect_global_nonlocal_decls(scope, &node.body);
+                }
+                ast::Stmt::Try(node) => {
+                    self.collect_global_nonlocal_decls(scope, &node.body);
+                    for handler in &node.handlers {
+                        let ast::ExceptHandler::ExceptHandler(handler) = handler;
+                        self.collect_global_nonlocal_decls(scope, &handler.body);
+                    }
+                    self.collect_global_nonlocal_decls(scope, &node.orelse);
+                    self.collect_global_nonlocal_decls(scope, &node.finalbody);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Finds the nearest enclosing function scope (skipping class scopes, which `nonlocal`
+    /// cannot bind into) whose own body textually binds `name` -- an assignment, `def`,
+    /// `class`, `import`, or `for`/`with` target anywhere in that scope (never descending
+    /// into a nested function/class body, which is a separate scope).
+    ///
+    /// This scans the ancestor's stored AST body rather than its (possibly still partially
+    /// built) symbol table, so it doesn't matter whether `name`'s binding in that ancestor
+    /// appears lexically before or after the `nonlocal` declaration being resolved here.
+    fn enclosing_function_scope(&self, scope: FileScopeId, name: &Name) -> Option<FileScopeId> {
+        let mut current = self.scopes[scope].parent;
+
+        while let Some(candidate) = current {
+            if self.scopes[candidate].kind == ScopeKind::Function {
+                if let Some(body) = self.scope_body(candidate) {
+                    if body_binds_name(body, name) {
+                        return Some(candidate);
+                    }
+                }
+            }
+
+            current = self.scopes[candidate].parent;
+        }
+
+        None
     }
 
     fn with_type_params(
@@ -180,10 +399,19 @@ impl<'a> SemanticIndexBuilder<'a> {
             .map(super::ast_ids::AstIdsBuilder::finish)
             .collect();
 
+        let mut flow_graphs: IndexVec<_, _> = self
+            .flow_graphs
+            .into_iter()
+            .map(FlowGraphBuilder::finish)
+            .collect();
+
         self.scopes.shrink_to_fit();
         ast_ids.shrink_to_fit();
         symbol_tables.shrink_to_fit();
+        flow_graphs.shrink_to_fit();
         self.scopes_by_expression.shrink_to_fit();
+        self.uses_flow_node.shrink_to_fit();
+        self.star_imports.shrink_to_fit();
 
         SemanticIndex {
             symbol_tables,
@@ -191,6 +419,9 @@ impl<'a> SemanticIndexBuilder<'a> {
             ast_ids,
             scopes_by_definition: self.scopes_by_definition,
             scopes_by_expression: self.scopes_by_expression,
+            flow_graphs,
+            uses_flow_node: self.uses_flow_node,
+            star_imports: self.star_imports,
         }
     }
 
@@ -212,6 +443,11 @@ impl<'a> SemanticIndexBuilder<'a> {
                     }
                     _ => {
                         self.add_or_update_symbol(Name::new(id), flags);
+
+                        if flags.contains(SymbolFlags::IS_USED) {
+                            self.uses_flow_node
+                                .insert(NodeKey::from_node(expr), self.current_flow_node());
+                        }
                     }
                 }
 
@@ -235,22 +471,21 @@ impl<'a> SemanticIndexBuilder<'a> {
 
                 self.visit_expr(test);
 
-                // let if_branch = self.flow_graph_builder.add_branch(self.current_flow_node());
+                let pre_branch = self.current_flow_node();
+                let constraint = NodeKey::from_node(test.as_ref());
 
-                // self.set_current_flow_node(if_branch);
-                // self.insert_constraint(test);
+                let if_branch = self.current_flow_graph().add_branch(pre_branch, constraint, false);
+                self.set_current_flow_node(if_branch);
                 self.visit_expr(body);
+                let post_body = self.current_flow_node();
 
-                // let post_body = self.current_flow_node();
-
-                // self.set_current_flow_node(if_branch);
+                let else_branch = self.current_flow_graph().add_branch(pre_branch, constraint, true);
+                self.set_current_flow_node(else_branch);
                 self.visit_expr(orelse);
+                let post_else = self.current_flow_node();
 
-                // let post_else = self
-                //     .flow_graph_builder
-                //     .add_phi(self.current_flow_node(), post_body);
-
-                // self.set_current_flow_node(post_else);
+                let post_if = self.current_flow_graph().add_phi(post_body, post_else);
+                self.set_current_flow_node(post_if);
             }
             _ => {
                 walk_expr(self, expr);
@@ -303,6 +538,12 @@ impl Visitor<'_> for SemanticIndexBuilder<'_> {
                             name,
                             function_def,
                         ));
+                        let function_scope = builder.current_scope();
+                        builder.store_scope_body(
+                            function_scope,
+                            ScopeBodyKey::Node(NodeKey::from_node(function_def)),
+                        );
+                        builder.collect_global_nonlocal_decls(function_scope, &function_def.body);
                         builder.visit_body(&function_def.body);
                         builder.pop_scope()
                     },
@@ -341,6 +582,10 @@ impl Visitor<'_> for SemanticIndexBuilder<'_> {
                             name,
                             class,
                         ));
+                        let class_scope = builder.current_scope();
+                        builder
+                            .store_scope_body(class_scope, ScopeBodyKey::Node(NodeKey::from_node(class)));
+                        builder.collect_global_nonlocal_decls(class_scope, &class.body);
                         builder.visit_body(&class.body);
 
                         builder.pop_scope()
@@ -369,14 +614,26 @@ impl Visitor<'_> for SemanticIndexBuilder<'_> {
                 }
             }
             ast::Stmt::ImportFrom(ast::StmtImportFrom {
-                module: _,
+                module: from_module,
                 names,
-                level: _,
+                level,
                 ..
             }) => {
                 let scope_id = self.current_scope();
 
                 for alias in names {
+                    // `from module import *` has no `asname` and its name is literally `*`;
+                    // it introduces a set of names that can only be known once the imported
+                    // module itself is resolved, so it has no single `Definition` and is
+                    // tracked separately via `star_imports` instead.
+                    if alias.name.id.as_str() == "*" {
+                        self.star_imports[scope_id].push(StarImport {
+                            module: from_module.as_ref().map(|m| Name::new(&m.id)),
+                            level: level.unwrap_or(0),
+                        });
+                        continue;
+                    }
+
                     let symbol_name = if let Some(asname) = &alias.asname {
                         asname.id.as_str()
                     } else {
@@ -412,6 +669,162 @@ impl Visitor<'_> for SemanticIndexBuilder<'_> {
                     self.current_definition = None;
                 }
             }
+            ast::Stmt::If(ast::StmtIf {
+                test,
+                body,
+                elif_else_clauses,
+                ..
+            }) => {
+                self.visit_expr(test);
+
+                let pre_branch = self.current_flow_node();
+                let constraint = NodeKey::from_node(test.as_ref());
+
+                let then_branch = self.current_flow_graph().add_branch(pre_branch, constraint, false);
+                self.set_current_flow_node(then_branch);
+                self.visit_body(body);
+                let mut post_branches = vec![self.current_flow_node()];
+
+                let mut pre_next = self.current_flow_graph().add_branch(pre_branch, constraint, true);
+                let mut had_else = false;
+
+                for clause in elif_else_clauses {
+                    self.set_current_flow_node(pre_next);
+
+                    if let Some(elif_test) = &clause.test {
+                        self.visit_expr(elif_test);
+                        let elif_pre = self.current_flow_node();
+                        let elif_constraint = NodeKey::from_node(elif_test);
+
+                        let elif_branch =
+                            self.current_flow_graph().add_branch(elif_pre, elif_constraint, false);
+                        self.set_current_flow_node(elif_branch);
+                        self.visit_body(&clause.body);
+                        post_branches.push(self.current_flow_node());
+
+                        pre_next = self.current_flow_graph().add_branch(elif_pre, elif_constraint, true);
+                    } else {
+                        had_else = true;
+                        self.visit_body(&clause.body);
+                        post_branches.push(self.current_flow_node());
+                    }
+                }
+
+                // No final `else`: control can also reach past the `if` without taking any
+                // branch at all.
+                if !had_else {
+                    post_branches.push(pre_next);
+                }
+
+                let post_if = post_branches
+                    .into_iter()
+                    .reduce(|first, second| self.current_flow_graph().add_phi(first, second))
+                    .expect("the `if` branch's flow node is always present");
+                self.set_current_flow_node(post_if);
+            }
+            ast::Stmt::While(ast::StmtWhile {
+                test, body, orelse, ..
+            }) => {
+                self.visit_expr(test);
+
+                let pre_loop = self.current_flow_node();
+                let constraint = NodeKey::from_node(test.as_ref());
+
+                let loop_entry = self.current_flow_graph().add_branch(pre_loop, constraint, false);
+                self.set_current_flow_node(loop_entry);
+                self.visit_body(body);
+                let after_body = self.current_flow_node();
+
+                let loop_exit = self.current_flow_graph().add_branch(pre_loop, constraint, true);
+                let post_loop = self.current_flow_graph().add_phi(after_body, loop_exit);
+                self.set_current_flow_node(post_loop);
+                self.visit_body(orelse);
+            }
+            ast::Stmt::For(ast::StmtFor {
+                target,
+                iter,
+                body,
+                orelse,
+                ..
+            }) => {
+                self.visit_expr(iter);
+
+                #[allow(unsafe_code)]
+                let expression_id = unsafe {
+                    // SAFETY: The builder only visits nodes that are part of `module`. This guarantees that
+                    // the current expression must be a child of `module`.
+                    self.current_ast_ids().record_expression(target, module)
+                };
+                self.current_definition = Some(Definition::Target(expression_id));
+                self.visit_expression_with_id(target, expression_id);
+                self.current_definition = None;
+
+                let pre_loop = self.current_flow_node();
+                // A `for` loop has no single boolean test to record as a `Branch` constraint;
+                // we fork on the iterable expression itself as a stand-in for "there are more
+                // items", which is all `reaching_definitions` needs to treat the loop body as
+                // conditionally executed.
+                let constraint = NodeKey::from_node(iter.as_ref());
+
+                let loop_entry = self.current_flow_graph().add_branch(pre_loop, constraint, false);
+                self.set_current_flow_node(loop_entry);
+                self.visit_body(body);
+                let after_body = self.current_flow_node();
+
+                let loop_exit = self.current_flow_graph().add_branch(pre_loop, constraint, true);
+                let post_loop = self.current_flow_graph().add_phi(after_body, loop_exit);
+                self.set_current_flow_node(post_loop);
+                self.visit_body(orelse);
+            }
+            ast::Stmt::Try(ast::StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                let pre_try = self.current_flow_node();
+                self.visit_body(body);
+                let body_success = self.current_flow_node();
+                let mut post_branches = Vec::new();
+
+                for handler in handlers {
+                    let ast::ExceptHandler::ExceptHandler(handler) = handler;
+
+                    // Any statement in the `try` body could have raised before completing, so
+                    // each handler forks off the flow node from *before* the body ran, not
+                    // after: the body's own bindings aren't guaranteed to have happened.
+                    self.set_current_flow_node(pre_try);
+                    if let Some(type_) = &handler.type_ {
+                        self.visit_expr(type_);
+                    }
+                    if let Some(name) = &handler.name {
+                        self.add_or_update_symbol(Name::new(&name.id), SymbolFlags::IS_DEFINED);
+                    }
+                    self.visit_body(&handler.body);
+                    post_branches.push(self.current_flow_node());
+                }
+
+                // `else` only runs when the `try` body completed with no exception, so it
+                // forks from the body's own success exit, not from a handler's: a name bound
+                // only inside an `except` must not be visible there.
+                self.set_current_flow_node(body_success);
+                self.visit_body(orelse);
+                let post_else = self.current_flow_node();
+
+                let post_try = post_branches
+                    .into_iter()
+                    .chain(std::iter::once(post_else))
+                    .reduce(|first, second| self.current_flow_graph().add_phi(first, second))
+                    .expect("the `try` body's own flow node is always present");
+                self.set_current_flow_node(post_try);
+
+                self.visit_body(finalbody);
+            }
+            // `global`/`nonlocal` declarations are collected up front by
+            // `collect_global_nonlocal_decls` when the enclosing scope is pushed, so there's
+            // nothing left to do when the visitor actually reaches the statement itself.
+            ast::Stmt::Global(_) | ast::Stmt::Nonlocal(_) => {}
             _ => {
                 walk_stmt(self, stmt);
             }
@@ -431,6 +844,129 @@ impl Visitor<'_> for SemanticIndexBuilder<'_> {
     }
 }
 
+/// Finds the body of the `Function`/`ClassDef` statement identified by `key`, searching `body`
+/// and, recursively, every nested statement (including other functions and classes, since a
+/// scope can be nested arbitrarily deep inside others).
+fn find_scope_body(body: &[ast::Stmt], key: NodeKey) -> Option<&[ast::Stmt]> {
+    for stmt in body {
+        let found = match stmt {
+            ast::Stmt::FunctionDef(node) => {
+                if NodeKey::from_node(node) == key {
+                    return Some(&node.body);
+                }
+                find_scope_body(&node.body, key)
+            }
+            ast::Stmt::ClassDef(node) => {
+                if NodeKey::from_node(node) == key {
+                    return Some(&node.body);
+                }
+                find_scope_body(&node.body, key)
+            }
+            ast::Stmt::If(node) => find_scope_body(&node.body, key).or_else(|| {
+                node.elif_else_clauses
+                    .iter()
+                    .find_map(|clause| find_scope_body(&clause.body, key))
+            }),
+            ast::Stmt::While(node) => find_scope_body(&node.body, key)
+                .or_else(|| find_scope_body(&node.orelse, key)),
+            ast::Stmt::For(node) => find_scope_body(&node.body, key)
+                .or_else(|| find_scope_body(&node.orelse, key)),
+            ast::Stmt::With(node) => find_scope_body(&node.body, key),
+            ast::Stmt::Try(node) => find_scope_body(&node.body, key)
+                .or_else(|| {
+                    node.handlers.iter().find_map(|handler| {
+                        let ast::ExceptHandler::ExceptHandler(handler) = handler;
+                        find_scope_body(&handler.body, key)
+                    })
+                })
+                .or_else(|| find_scope_body(&node.orelse, key))
+                .or_else(|| find_scope_body(&node.finalbody, key)),
+            _ => None,
+        };
+
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    None
+}
+
+/// Returns whether any statement in `body` binds `name` -- an assignment, `def`, `class`,
+/// `import`, or `for`/`with` target -- without descending into a nested function or class
+/// body, which is a separate scope with its own bindings.
+fn body_binds_name(body: &[ast::Stmt], name: &Name) -> bool {
+    body.iter().any(|stmt| stmt_binds_name(stmt, name))
+}
+
+fn stmt_binds_name(stmt: &ast::Stmt, name: &Name) -> bool {
+    match stmt {
+        ast::Stmt::Assign(node) => node.targets.iter().any(|target| target_binds_name(target, name)),
+        ast::Stmt::AugAssign(node) => target_binds_name(&node.target, name),
+        ast::Stmt::AnnAssign(node) => target_binds_name(&node.target, name),
+        ast::Stmt::FunctionDef(node) => Name::new(&node.name.id) == *name,
+        ast::Stmt::ClassDef(node) => Name::new(&node.name.id) == *name,
+        ast::Stmt::Import(node) => node.names.iter().any(|alias| alias_binds_name(alias, name)),
+        ast::Stmt::ImportFrom(node) => node.names.iter().any(|alias| alias_binds_name(alias, name)),
+        ast::Stmt::For(node) => {
+            target_binds_name(&node.target, name)
+                || body_binds_name(&node.body, name)
+                || body_binds_name(&node.orelse, name)
+        }
+        ast::Stmt::While(node) => {
+            body_binds_name(&node.body, name) || body_binds_name(&node.orelse, name)
+        }
+        ast::Stmt::If(node) => {
+            body_binds_name(&node.body, name)
+                || node
+                    .elif_else_clauses
+                    .iter()
+                    .any(|clause| body_binds_name(&clause.body, name))
+        }
+        ast::Stmt::With(node) => {
+            node.items.iter().any(|item| {
+                item.optional_vars
+                    .as_deref()
+                    .is_some_and(|target| target_binds_name(target, name))
+            }) || body_binds_name(&node.body, name)
+        }
+        ast::Stmt::Try(node) => {
+            body_binds_name(&node.body, name)
+                || node.handlers.iter().any(|handler| {
+                    let ast::ExceptHandler::ExceptHandler(handler) = handler;
+                    handler
+                        .name
+                        .as_ref()
+                        .is_some_and(|bound| Name::new(&bound.id) == *name)
+                        || body_binds_name(&handler.body, name)
+                })
+                || body_binds_name(&node.orelse, name)
+                || body_binds_name(&node.finalbody, name)
+        }
+        _ => false,
+    }
+}
+
+fn target_binds_name(target: &ast::Expr, name: &Name) -> bool {
+    match target {
+        ast::Expr::Name(ast::ExprName { id, .. }) => Name::new(id) == *name,
+        ast::Expr::Tuple(ast::ExprTuple { elts, .. }) | ast::Expr::List(ast::ExprList { elts, .. }) => {
+            elts.iter().any(|elt| target_binds_name(elt, name))
+        }
+        ast::Expr::Starred(ast::ExprStarred { value, .. }) => target_binds_name(value, name),
+        _ => false,
+    }
+}
+
+fn alias_binds_name(alias: &ast::Alias, name: &Name) -> bool {
+    let symbol_name = if let Some(asname) = &alias.asname {
+        asname.id.as_str()
+    } else {
+        alias.name.id.split('.').next().unwrap()
+    };
+    Name::new(symbol_name) == *name
+}
+
 enum WithTypeParams<'a> {
     ClassDef {
         node: &'a ast::StmtClassDef,
@@ -451,6 +987,58 @@ impl<'a> WithTypeParams<'a> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use red_knot_module_resolver::{set_module_resolution_settings, ModuleResolutionSettings};
+    use ruff_db::file_system::FileSystemPathBuf;
+    use ruff_db::vfs::system_path_to_file;
+
+    use crate::db::tests::TestDb;
+    use crate::semantic_index::semantic_index;
+    use crate::semantic_index::symbol::FileScopeId;
+
+    fn setup_db() -> TestDb {
+        let mut db = TestDb::new();
+        set_module_resolution_settings(
+            &mut db,
+            ModuleResolutionSettings {
+                extra_paths: vec![],
+                workspace_root: FileSystemPathBuf::from("/src"),
+                site_packages: None,
+                custom_typeshed: None,
+            },
+        );
+
+        db
+    }
+
+    #[test]
+    fn global_declared_after_first_use_still_redirects_the_whole_function() -> anyhow::Result<()> {
+        let db = setup_db();
+
+        db.memory_file_system().write_file(
+            "/src/foo.py",
+            "x = 0\ndef f():\n    print(x)\n    global x\n    x = 1\n",
+        )?;
+        let foo = system_path_to_file(&db, "/src/foo.py").unwrap();
+
+        let index = semantic_index(&db, foo);
+        let module_scope = FileScopeId::from_usize(0);
+
+        // Every reference to `x` inside `f`, including the one *before* the `global x`
+        // line, was redirected to the module scope: `f` never locally binds `x` at all.
+        let f_scope = index
+            .scope_ids()
+            .find(|scope| *scope != module_scope)
+            .expect("f introduces its own scope");
+
+        assert!(index.symbol_table(f_scope).symbol_id_by_name("x").is_none());
+        assert!(index.symbol_table(module_scope).symbol_id_by_name("x").is_some());
+
+        Ok(())
+    }
+}
+
 struct NodeWithScope {
     id: NodeWithScopeId,
     definition_key: Option<DefinitionNodeKey>,
@@ -0,0 +1,194 @@
+use ruff_index::{newtype_index, IndexVec};
+
+use crate::node_key::NodeKey;
+use crate::semantic_index::definition::Definition;
+use crate::semantic_index::symbol::ScopedSymbolId;
+
+#[newtype_index]
+pub struct FlowNodeId;
+
+/// A node in the per-scope control-flow graph.
+///
+/// The graph is built bottom-up as the AST is visited: each binding and each branch point
+/// forks a new node off the "current" flow node, and merging control paths (the end of an
+/// `if`/`else`, a loop, a `try`/`except`) insert a [`FlowNode::Phi`] joining them back up.
+/// A later `reaching_definitions` query walks this graph backwards from a use to find every
+/// definition that can reach it, which is what narrowing (`if isinstance(x, int): ...`)
+/// needs.
+#[derive(Debug, Clone)]
+pub enum FlowNode {
+    /// The entry point of the scope; has no predecessor.
+    Start,
+    /// `symbol` is bound to `definition` here.
+    Definition {
+        symbol: ScopedSymbolId,
+        definition: Definition,
+        predecessor: FlowNodeId,
+    },
+    /// Control reaches this point only if `constraint` evaluated truthy (or falsy, if
+    /// `negated`) on the way from `predecessor`.
+    Branch {
+        constraint: NodeKey,
+        negated: bool,
+        predecessor: FlowNodeId,
+    },
+    /// Where two control-flow paths (e.g. the `if` and `else` arms) join back together.
+    Phi {
+        first: FlowNodeId,
+        second: FlowNodeId,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FlowGraph {
+    nodes: IndexVec<FlowNodeId, FlowNode>,
+}
+
+impl FlowGraph {
+    pub(crate) fn node(&self, id: FlowNodeId) -> &FlowNode {
+        &self.nodes[id]
+    }
+
+    /// Returns every [`Definition`] that may reach `use_node`, i.e. every definition reachable
+    /// by walking predecessors backwards from `use_node` without crossing a later definition
+    /// of the same symbol on that path.
+    pub fn reaching_definitions(&self, use_node: FlowNodeId) -> Vec<Definition> {
+        let mut definitions = Vec::new();
+        // Each entry carries the symbols already defined on *its own* path back from the use.
+        let mut stack = vec![(use_node, Vec::<ScopedSymbolId>::new())];
+        let mut visited = rustc_hash::FxHashSet::default();
+
+        while let Some((node, mut killed)) = stack.pop() {
+            if !visited.insert((node, killed.clone())) {
+                continue;
+            }
+
+            match self.node(node) {
+                FlowNode::Start => {}
+                FlowNode::Definition {
+                    symbol,
+                    definition,
+                    predecessor,
+                } => {
+                    // Record the first definition of this symbol on this path, but always keep
+                    // walking backwards: an earlier definition of some *other* symbol on this
+                    // same path is still reachable and hasn't been found yet.
+                    if !killed.contains(symbol) {
+                        killed.push(*symbol);
+                        if !definitions.contains(definition) {
+                            definitions.push(*definition);
+                        }
+                    }
+                    stack.push((*predecessor, killed));
+                }
+                FlowNode::Branch { predecessor, .. } => stack.push((*predecessor, killed)),
+                FlowNode::Phi { first, second } => {
+                    stack.push((*first, killed.clone()));
+                    stack.push((*second, killed));
+                }
+            }
+        }
+
+        definitions
+    }
+}
+
+/// Incrementally builds a [`FlowGraph`] while the AST is visited.
+#[derive(Debug)]
+pub(super) struct FlowGraphBuilder {
+    nodes: IndexVec<FlowNodeId, FlowNode>,
+}
+
+impl FlowGraphBuilder {
+    pub(super) fn new() -> Self {
+        let mut nodes = IndexVec::new();
+        nodes.push(FlowNode::Start);
+        Self { nodes }
+    }
+
+    pub(super) fn start(&self) -> FlowNodeId {
+        FlowNodeId::from_usize(0)
+    }
+
+    pub(super) fn add_definition(
+        &mut self,
+        symbol: ScopedSymbolId,
+        definition: Definition,
+        predecessor: FlowNodeId,
+    ) -> FlowNodeId {
+        self.nodes.push(FlowNode::Definition {
+            symbol,
+            definition,
+            predecessor,
+        })
+    }
+
+    pub(super) fn add_branch(
+        &mut self,
+        predecessor: FlowNodeId,
+        constraint: NodeKey,
+        negated: bool,
+    ) -> FlowNodeId {
+        self.nodes.push(FlowNode::Branch {
+            constraint,
+            negated,
+            predecessor,
+        })
+    }
+
+    pub(super) fn add_phi(&mut self, first: FlowNodeId, second: FlowNodeId) -> FlowNodeId {
+        self.nodes.push(FlowNode::Phi { first, second })
+    }
+
+    pub(super) fn finish(self) -> FlowGraph {
+        FlowGraph { nodes: self.nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic_index::ast_ids::ScopedExpressionId;
+    use crate::semantic_index::symbol::ScopedSymbolId;
+
+    #[test]
+    fn reaching_definitions_finds_earlier_siblings_not_just_the_first_hit() {
+        // x = 1; y = 2; use(x, y) -- walking back from the use should find *both*
+        // definitions, not stop at whichever symbol's definition node is hit first.
+        let mut builder = FlowGraphBuilder::new();
+        let x = ScopedSymbolId::from_usize(0);
+        let y = ScopedSymbolId::from_usize(1);
+
+        let x_def = Definition::Target(ScopedExpressionId::from_usize(0));
+        let y_def = Definition::Target(ScopedExpressionId::from_usize(1));
+
+        let after_x = builder.add_definition(x, x_def, builder.start());
+        let after_y = builder.add_definition(y, y_def, after_x);
+
+        let graph = builder.finish();
+        let mut found = graph.reaching_definitions(after_y);
+        found.sort_by_key(|definition| format!("{definition:?}"));
+
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&x_def));
+        assert!(found.contains(&y_def));
+    }
+
+    #[test]
+    fn reaching_definitions_stops_at_the_nearest_redefinition() {
+        // x = 1; x = 2; use(x) -- only the closer definition of `x` reaches the use.
+        let mut builder = FlowGraphBuilder::new();
+        let x = ScopedSymbolId::from_usize(0);
+
+        let first = Definition::Target(ScopedExpressionId::from_usize(0));
+        let second = Definition::Target(ScopedExpressionId::from_usize(1));
+
+        let after_first = builder.add_definition(x, first, builder.start());
+        let after_second = builder.add_definition(x, second, after_first);
+
+        let graph = builder.finish();
+        let found = graph.reaching_definitions(after_second);
+
+        assert_eq!(found, vec![second]);
+    }
+}
@@ -0,0 +1,60 @@
+use ruff_text_size::{Ranged, TextSize};
+
+use crate::semantic_index::symbol::{FileScopeId, ScopeKind, ScopedSymbolId};
+use crate::semantic_index::SemanticIndex;
+
+impl SemanticIndex {
+    /// Returns the deepest scope whose node range contains `offset`.
+    ///
+    /// Scopes are stored in preorder, and each [`Scope::descendents`] range spans that
+    /// scope's entire subtree, so descending one level at a time only costs a linear scan of
+    /// each level's *siblings*: a child whose range doesn't contain `offset` lets us skip
+    /// straight past its whole subtree to the next sibling.
+    pub fn scope_at_offset(&self, offset: TextSize) -> FileScopeId {
+        let mut current = FileScopeId::from_usize(0);
+
+        loop {
+            let children = self.scopes[current].descendents.clone();
+            let mut child = children.start;
+            let mut descended = None;
+
+            while child < children.end {
+                if self.scope_node_range(child).contains(&offset) {
+                    descended = Some(child);
+                    break;
+                }
+
+                // Not this child: skip past its entire subtree to its next sibling.
+                child = self.scopes[child].descendents.end;
+            }
+
+            match descended {
+                Some(child) => current = child,
+                None => return current,
+            }
+        }
+    }
+
+    /// Collects every [`ScopedSymbolId`] reachable from the scope enclosing `offset`, walking
+    /// up the parent chain and skipping class scopes per Python's LEGB rule. This gives
+    /// completion a ready-made list of names that are legal to reference at that position.
+    pub fn visible_symbols_at(&self, offset: TextSize) -> Vec<ScopedSymbolId> {
+        let innermost = self.scope_at_offset(offset);
+        let mut visible = Vec::new();
+        let mut scope = Some(innermost);
+
+        while let Some(current) = scope {
+            if current == innermost || self.scopes[current].kind != ScopeKind::Class {
+                visible.extend(self.symbol_table(current).symbol_ids());
+            }
+
+            scope = self.scopes[current].parent;
+        }
+
+        visible
+    }
+
+    fn scope_node_range(&self, scope: FileScopeId) -> ruff_text_size::TextRange {
+        self.ast_ids[scope].scope_node_range(self.scopes[scope].node)
+    }
+}
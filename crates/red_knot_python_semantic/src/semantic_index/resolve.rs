@@ -0,0 +1,121 @@
+use crate::semantic_index::symbol::{FileScopeId, ScopeKind, ScopedSymbolId};
+use crate::semantic_index::SemanticIndex;
+
+/// Where a resolved name is bound, relative to the scope it was looked up from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionCategory {
+    /// Bound in the scope the lookup started from.
+    Local,
+    /// Bound in an enclosing function scope (Python's "E" in LEGB).
+    Enclosing,
+    /// Bound in the module scope ("G" in LEGB).
+    Global,
+    /// Not found in any enclosing scope; falls back to the implicit builtins scope ("B").
+    Builtin,
+}
+
+/// The result of resolving a name used in some scope to the scope and symbol that binds it.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedSymbol {
+    pub scope: FileScopeId,
+    pub symbol: ScopedSymbolId,
+    pub category: ResolutionCategory,
+}
+
+impl SemanticIndex {
+    /// Resolves `name`, used in `file_scope`, to the binding it refers to.
+    ///
+    /// This walks `file_scope`'s symbol table, then `Scope::parent` outward, skipping class
+    /// scopes along the way (Python's LEGB rule: a class body is not visible to a function
+    /// nested inside it). If no enclosing scope binds the name, resolution falls back to the
+    /// implicit builtins scope.
+    pub fn resolve_use(&self, file_scope: FileScopeId, name: &str) -> Option<ResolvedSymbol> {
+        let mut scope = Some(file_scope);
+        let mut category = ResolutionCategory::Local;
+
+        while let Some(current) = scope {
+            let is_visible = current == file_scope || self.scope(current).kind() != ScopeKind::Class;
+
+            if is_visible {
+                let table = self.symbol_table(current);
+                // A scope that only *uses* the name (it was loaded there) doesn't bind it.
+                if let Some(symbol) = table
+                    .symbol_id_by_name(name)
+                    .filter(|symbol| table.symbol(*symbol).is_defined())
+                {
+                    return Some(ResolvedSymbol {
+                        scope: current,
+                        symbol,
+                        category,
+                    });
+                }
+            }
+
+            scope = self.scope(current).parent();
+            category = match scope.map(|parent| self.scope(parent).kind()) {
+                Some(ScopeKind::Module) => ResolutionCategory::Global,
+                _ => ResolutionCategory::Enclosing,
+            };
+        }
+
+        self.builtins_symbol_table()
+            .symbol_id_by_name(name)
+            .map(|symbol| ResolvedSymbol {
+                scope: self.builtins_scope(),
+                symbol,
+                category: ResolutionCategory::Builtin,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use red_knot_module_resolver::{set_module_resolution_settings, ModuleResolutionSettings};
+    use ruff_db::file_system::FileSystemPathBuf;
+    use ruff_db::vfs::system_path_to_file;
+    use ruff_text_size::TextSize;
+
+    use crate::db::tests::TestDb;
+    use crate::semantic_index::semantic_index;
+
+    fn setup_db() -> TestDb {
+        let mut db = TestDb::new();
+        set_module_resolution_settings(
+            &mut db,
+            ModuleResolutionSettings {
+                extra_paths: vec![],
+                workspace_root: FileSystemPathBuf::from("/src"),
+                site_packages: None,
+                custom_typeshed: None,
+            },
+        );
+
+        db
+    }
+
+    #[test]
+    fn resolve_use_skips_class_scope_for_a_nested_method() -> anyhow::Result<()> {
+        let db = setup_db();
+        let source = "\
+class C:
+    x = 1
+
+    def m(self):
+        return x
+";
+        db.memory_file_system().write_file("/src/foo.py", source)?;
+        let foo = system_path_to_file(&db, "/src/foo.py").unwrap();
+
+        let index = semantic_index(&db, foo);
+        let offset =
+            TextSize::try_from(source.find("return x").unwrap() + "return ".len()).unwrap();
+        let method_scope = index.scope_at_offset(offset);
+
+        // `x` is only bound on the class body, which is invisible to a method nested inside
+        // it per LEGB: it must not resolve there and must fall through to module/builtins,
+        // where it also isn't bound.
+        assert!(index.resolve_use(method_scope, "x").is_none());
+
+        Ok(())
+    }
+}
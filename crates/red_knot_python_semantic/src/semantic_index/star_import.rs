@@ -0,0 +1,33 @@
+use ruff_db::vfs::VfsFile;
+
+use crate::semantic_index::builder::StarImport;
+use crate::semantic_index::symbol::FileScopeId;
+use crate::semantic_index::{semantic_index, SemanticIndex};
+use crate::Db;
+
+impl SemanticIndex {
+    /// Returns every `from <module> import *` appearing directly in `file_scope`.
+    pub fn star_imports(&self, file_scope: FileScopeId) -> &[StarImport] {
+        &self.star_imports[file_scope]
+    }
+}
+
+/// Materializes the set of names a `from <module> import *` would bind, given the *source*
+/// module's own file. This is the same expansion an IDE performs when rewriting a star import
+/// into an explicit list, but here it drives unresolved-name resolution instead: respects
+/// `__all__` when the source module defines one, and otherwise falls back to every
+/// non-underscore name bound at that module's top level.
+pub fn expand_star_import(db: &dyn Db, source_file: VfsFile) -> Vec<String> {
+    let index = semantic_index(db, source_file);
+
+    if let Some(dunder_all) = index.dunder_all() {
+        return dunder_all.to_vec();
+    }
+
+    index
+        .symbol_table(index.module_scope())
+        .symbol_names()
+        .filter(|name| !name.starts_with('_'))
+        .map(str::to_string)
+        .collect()
+}
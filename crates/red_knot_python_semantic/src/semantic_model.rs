@@ -1,11 +1,16 @@
 use red_knot_module_resolver::Module;
 use ruff_db::vfs::VfsFile;
 use ruff_python_ast as ast;
+use ruff_python_ast::visitor::{walk_expr, Visitor};
 use ruff_python_ast::{Expr, ExpressionRef};
+use ruff_text_size::{Ranged, TextRange, TextSize};
 
+use ruff_diagnostics::Diagnostic;
+
+use crate::diagnostic::TypeCheckDiagnostics;
 use crate::semantic_index::ast_ids::HasScopedAstId;
 use crate::semantic_index::definition::{Definition, DefinitionNodeKey};
-use crate::semantic_index::symbol::PublicSymbolId;
+use crate::semantic_index::symbol::{FileScopeId, PublicSymbolId, ScopeKind};
 use crate::semantic_index::{public_symbol, semantic_index};
 use crate::types::{infer_types, public_symbol_ty, Type, TypingContext};
 use crate::Db;
@@ -31,8 +36,179 @@ impl<'db> SemanticModel<'db> {
     pub fn typing_context(&self) -> TypingContext<'db, '_> {
         TypingContext::global(self.db)
     }
+
+    /// Resolves `expr` to the `Definition` that introduced the name or member it refers to.
+    ///
+    /// For a `Name`, this defers to [`SemanticIndex::resolve_use`], which walks the scope
+    /// chain outward from the innermost scope enclosing `expr` the same way Python's LEGB
+    /// rule does, skipping class scopes except the one the name is used in directly (a name
+    /// used in a method cannot see its enclosing class's attributes). For an `Attribute`,
+    /// this infers the type of the attribute's value and resolves the member on that type.
+    ///
+    /// Returns `None` if `expr` is not a `Name` or `Attribute`, or if the name has no
+    /// binding definition in scope (e.g. a builtin or an unresolved reference).
+    pub fn resolve(&self, expr: ExpressionRef) -> Option<Definition<'db>> {
+        match expr {
+            ExpressionRef::Name(name) => {
+                let index = semantic_index(self.db, self.file);
+                let file_scope = index.expression_scope_id(expr);
+                let resolved = index.resolve_use(file_scope, name.id.as_str())?;
+
+                index.symbol_table(resolved.scope).definition(resolved.symbol)
+            }
+            ExpressionRef::Attribute(attribute) => {
+                let value_ty = attribute.value.ty(self);
+                value_ty.member(self.db, attribute.attr.id.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the lexical scope enclosing `offset`.
+    pub fn scope_at_offset(&self, offset: TextSize) -> SemanticScope<'db> {
+        let index = semantic_index(self.db, self.file);
+        let file_scope = index.scope_at_offset(offset);
+
+        SemanticScope {
+            db: self.db,
+            file: self.file,
+            file_scope,
+        }
+    }
+
+    /// Checks every name reference in this file and collects the diagnostics found along the
+    /// way. Currently this only reports unresolved references (a `Load` of a name with no
+    /// binding anywhere in its scope chain, including builtins); checks that need real type
+    /// inference (calls to non-callables, bad subscripts, argument/return type mismatches)
+    /// aren't implemented yet.
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let index = semantic_index(self.db, self.file);
+        let mut diagnostics = TypeCheckDiagnostics::default();
+
+        let parsed = ruff_db::parsed::parsed_module(self.db, self.file);
+        let mut checker = UnresolvedReferenceChecker {
+            index: &index,
+            diagnostics: &mut diagnostics,
+        };
+        for stmt in parsed.suite() {
+            checker.visit_stmt(stmt);
+        }
+
+        diagnostics.into_vec()
+    }
+
+    /// Returns every expression across the workspace that resolves to `def`.
+    ///
+    /// This is the inverse of [`SemanticModel::resolve`]: where `resolve` walks from a use
+    /// to its binding, `find_references` walks from a binding to every use of it.
+    pub fn find_references(&self, def: Definition<'db>) -> Vec<(VfsFile, TextRange)> {
+        let files = red_knot_module_resolver::all_modules(self.db).filter_map(|name| {
+            red_knot_module_resolver::resolve_module(self.db, name).map(|module| module.file())
+        });
+
+        crate::references::find_references(self.db, files, def)
+    }
+}
+
+/// The lexical scope enclosing a source position, together with every binding visible
+/// from it that is defined in this file (locals, parameters, enclosing-function captures and
+/// module globals). Builtins are not included.
+pub struct SemanticScope<'db> {
+    db: &'db dyn Db,
+    file: VfsFile,
+    file_scope: FileScopeId,
+}
+
+impl<'db> SemanticScope<'db> {
+    /// Returns every binding visible from this scope, as `(name, Definition, Type)`.
+    ///
+    /// Bindings are folded from this scope outward through its ancestors, so a name bound
+    /// in an inner scope shadows the same name bound further out. The walk ends at the module
+    /// scope: builtins are not included. Per Python's LEGB rule, ancestor class scopes are
+    /// skipped (a name isn't visible inside a nested function just because an enclosing
+    /// class body binds it) -- only this scope's own symbol table is considered even if it
+    /// is itself a class scope.
+    pub fn symbols(&self) -> Vec<(String, Definition<'db>, Type<'db>)> {
+        let index = semantic_index(self.db, self.file);
+        let mut seen = rustc_hash::FxHashSet::default();
+        let mut visible = Vec::new();
+
+        let mut file_scope = Some(self.file_scope);
+        while let Some(scope) = file_scope {
+            if scope == self.file_scope || index.scope(scope).kind() != ScopeKind::Class {
+                let table = index.symbol_table(scope);
+                let scope_id = scope.to_scope_id(self.db, self.file);
+                let types = infer_types(self.db, scope_id);
+
+                for (name, symbol) in table.symbols() {
+                    // Only a binding shadows outer scopes; a name that is merely used here
+                    // has no definition and must not hide an outer binding of it.
+                    if let Some(definition) = table.definition(symbol) {
+                        if !seen.insert(name.to_string()) {
+                            continue;
+                        }
+
+                        let ty = types.definition_ty(definition);
+                        visible.push((name.to_string(), definition, ty));
+                    }
+                }
+            }
+
+            file_scope = index.scope(scope).parent();
+        }
+
+        visible
+    }
+}
+
+/// Walks a file's statements looking for `Load`s of a name with no binding anywhere in its
+/// scope chain (not even a builtin), pushing an [`UnresolvedReference`](crate::diagnostic::UnresolvedReference)
+/// diagnostic for each one found.
+struct UnresolvedReferenceChecker<'a> {
+    index: &'a crate::semantic_index::SemanticIndex,
+    diagnostics: &'a mut TypeCheckDiagnostics,
+}
+
+impl Visitor<'_> for UnresolvedReferenceChecker<'_> {
+    fn visit_expr(&mut self, expr: &ast::Expr) {
+        if let ast::Expr::Name(ast::ExprName { id, ctx: ast::ExprContext::Load, .. }) = expr {
+            let file_scope = self.index.expression_scope_id(ExpressionRef::from(expr));
+
+            if self.index.resolve_use(file_scope, id.as_str()).is_none() {
+                self.diagnostics.unresolved_reference(id.as_str(), expr.range());
+            }
+        }
+
+        walk_expr(self, expr);
+    }
 }
 
+pub trait ResolvesTo {
+    /// Returns the `Definition` that introduces the binding `self` refers to, if any.
+    fn resolves_to<'db>(&self, model: &SemanticModel<'db>) -> Option<Definition<'db>>;
+}
+
+impl ResolvesTo for ast::ExpressionRef<'_> {
+    fn resolves_to<'db>(&self, model: &SemanticModel<'db>) -> Option<Definition<'db>> {
+        model.resolve(*self)
+    }
+}
+
+macro_rules! impl_expression_resolves_to {
+    ($ty: ty) => {
+        impl ResolvesTo for $ty {
+            #[inline]
+            fn resolves_to<'db>(&self, model: &SemanticModel<'db>) -> Option<Definition<'db>> {
+                let expression_ref = ExpressionRef::from(self);
+                expression_ref.resolves_to(model)
+            }
+        }
+    };
+}
+
+impl_expression_resolves_to!(ast::ExprName);
+impl_expression_resolves_to!(ast::ExprAttribute);
+
 pub trait HasTy {
     /// Returns the inferred`type` of `self`.
     ///
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use fst::automaton::{Automaton, Str};
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use ruff_db::vfs::VfsFile;
+use ruff_text_size::TextRange;
+
+use crate::semantic_index::symbol::PublicSymbolId;
+use crate::semantic_index::{public_symbols, semantic_index};
+use crate::Db;
+
+/// What kind of thing a workspace symbol refers to, for icon/filtering purposes in an editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Class,
+    Variable,
+    Module,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolRecord<'db> {
+    pub name: String,
+    pub symbol: PublicSymbolId<'db>,
+    pub file: VfsFile,
+    pub range: TextRange,
+    pub kind: SymbolKind,
+}
+
+/// An index of every public symbol across all resolved modules, searchable by exact,
+/// prefix, or fuzzy (bounded edit-distance) name.
+pub struct SymbolIndex<'db> {
+    /// Maps each distinct symbol name to the half-open range of `records` it occupies.
+    /// `records` is sorted by name so that a single contiguous range suffices.
+    map: Map<Vec<u8>>,
+    records: Vec<SymbolRecord<'db>>,
+}
+
+impl<'db> SymbolIndex<'db> {
+    /// Returns every record whose name exactly matches `query`.
+    pub fn exact(&self, query: &str) -> &[SymbolRecord<'db>] {
+        self.lookup(query).unwrap_or(&[])
+    }
+
+    /// Returns every record whose name starts with `query`.
+    pub fn prefix(&self, query: &str) -> Vec<&SymbolRecord<'db>> {
+        let automaton = Str::new(query).starts_with();
+        self.stream(automaton)
+    }
+
+    /// Returns every record whose name is within `max_edits` edits of `query`
+    /// (a Levenshtein automaton over the FST, for fuzzy workspace-symbol search).
+    pub fn fuzzy(&self, query: &str, max_edits: u32) -> Vec<&SymbolRecord<'db>> {
+        let Ok(automaton) = fst::automaton::Levenshtein::new(query, max_edits) else {
+            return self.prefix(query);
+        };
+        self.stream(automaton)
+    }
+
+    fn lookup(&self, name: &str) -> Option<&[SymbolRecord<'db>]> {
+        let packed = self.map.get(name)?;
+        let (start, len) = unpack(packed);
+        self.records.get(start..start + len)
+    }
+
+    fn stream<A: Automaton>(&self, automaton: A) -> Vec<&SymbolRecord<'db>> {
+        let mut stream = self.map.search(automaton).into_stream();
+        let mut results = Vec::new();
+
+        while let Some((_name, packed)) = stream.next() {
+            let (start, len) = unpack(packed);
+            results.extend(self.records[start..start + len].iter());
+        }
+
+        results
+    }
+}
+
+/// The incrementally-recomputed workspace symbol index.
+///
+/// Salsa only re-runs [`file_symbols`] for files that changed; this query then only pays the
+/// cost of rebuilding the (much cheaper) FST over the concatenation of those per-file results.
+#[salsa::tracked(return_ref)]
+pub fn symbol_index<'db>(db: &'db dyn Db) -> Arc<SymbolIndex<'db>> {
+    use rayon::prelude::*;
+
+    let mut records: Vec<SymbolRecord<'db>> = public_symbols(db)
+        .par_iter()
+        .flat_map(|file| file_symbols(db, *file).as_ref().clone())
+        .collect();
+
+    records.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut builder = MapBuilder::memory();
+    let mut index = 0;
+
+    while index < records.len() {
+        let name = records[index].name.as_str();
+        let start = index;
+        while index < records.len() && records[index].name == name {
+            index += 1;
+        }
+        builder
+            .insert(name, pack(start, index - start))
+            .expect("records are sorted by name, so names are inserted in order");
+    }
+
+    let map = Map::new(builder.into_inner().expect("building the FST in memory cannot fail"))
+        .expect("the bytes we just built are a valid FST");
+
+    Arc::new(SymbolIndex { map, records })
+}
+
+/// Every public symbol defined in `file`, as a salsa query so it is only recomputed when
+/// `file` itself changes.
+#[salsa::tracked(return_ref)]
+pub fn file_symbols(db: &dyn Db, file: VfsFile) -> Arc<Vec<SymbolRecord<'_>>> {
+    let index = semantic_index(db, file);
+    Arc::new(index.public_symbol_records(file))
+}
+
+fn pack(start: usize, len: usize) -> u64 {
+    (start as u64) << 32 | len as u64
+}
+
+fn unpack(packed: u64) -> (usize, usize) {
+    ((packed >> 32) as usize, (packed & 0xFFFF_FFFF) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, unpack};
+
+    #[test]
+    fn pack_unpack_round_trips() {
+        assert_eq!(unpack(pack(0, 0)), (0, 0));
+        assert_eq!(unpack(pack(3, 2)), (3, 2));
+        assert_eq!(unpack(pack(u32::MAX as usize, 1)), (u32::MAX as usize, 1));
+    }
+}